@@ -24,17 +24,25 @@
 
 mod helper;
 
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use clap::{ArgAction, Parser, Subcommand};
-use tracing::{debug, info};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use aws_smithy_types::DateTime as AwsDateTime;
+use aws_smithy_types::date_time::Format as AwsDateTimeFormat;
+use futures::stream::{self, StreamExt};
+use tracing::{debug, info, warn};
 use tracing_subscriber::EnvFilter;
 use tokio::fs::File;
 use tokio::io::{self, AsyncWrite, AsyncWriteExt};
 use aws_sdk_cognitoidentityprovider::Client as CognitoClient;
-use aws_sdk_cognitoidentityprovider::types::UserType;
+use aws_sdk_cognitoidentityprovider::types::{
+    AttributeType, GroupType, MessageActionType, UserStatusType, UserType,
+};
 
 
 const LONG_VERSION: &str = concat!(
@@ -75,6 +83,7 @@ struct Cli {
 /// - sync: synchronize users from a source into Cognito.
 /// - add: add users to one or more Cognito groups.
 /// - del: remove users from one or more Cognito groups.
+/// - groups: enumerate the groups in a pool.
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Synchronize users with a Cognito user pool.
@@ -85,6 +94,9 @@ enum Commands {
 
     /// Remove users from one or more Cognito groups.
     Del(GroupOperationArgs),
+
+    /// List the groups in a pool, optionally with membership counts.
+    Groups(GroupsArgs),
 }
 
 /// Common arguments shared by group-based operations.
@@ -94,6 +106,10 @@ pub struct CommonOperationArgs {
     #[arg(long = "pool-id", env = "COGNITO_USER_POOL_ID")]
     pub pool_id: String,
 
+    /// One or more Cognito group names involved in the operation.
+    #[arg(long = "group", alias = "groups")]
+    pub groups: Vec<String>,
+
     /// File path used by the operation.
     /// For `sync`, this is the output file where usernames and emails are stored as CSV.
     #[arg(
@@ -114,6 +130,10 @@ pub struct CommonOperationArgs {
 }
 
 /// Arguments for the `sync` operation.
+///
+/// Note: by default `sync` exports every user regardless of confirmation
+/// status, matching its historical behavior; pass `--exclude-unconfirmed`
+/// to drop `UNCONFIRMED` users, or `--unconfirmed-only` to see only those.
 #[derive(Debug, Parser)]
 struct SyncArgs {
     /// Cognito User Pool ID (e.g. us-east-1_XXXXXXXXX).
@@ -141,6 +161,218 @@ struct SyncArgs {
     /// Global timeout for the sync operation, in seconds.
     #[arg(long)]
     timeout: Option<u64>,
+
+    /// Only include users whose status is `UNCONFIRMED`.
+    #[arg(long = "unconfirmed-only")]
+    unconfirmed_only: bool,
+
+    /// Drop `UNCONFIRMED` users from the export.
+    ///
+    /// By default `sync` exports every user regardless of status; this
+    /// flag opts unconfirmed users back out.
+    #[arg(long = "exclude-unconfirmed")]
+    exclude_unconfirmed: bool,
+
+    /// Keep `UNCONFIRMED` users even if `--exclude-unconfirmed` is set.
+    ///
+    /// Only meaningful alongside `--exclude-unconfirmed`; has no effect on
+    /// its own since unconfirmed users are included by default.
+    #[arg(long = "include-unconfirmed")]
+    include_unconfirmed: bool,
+
+    /// Only include users created at or after this RFC 3339 timestamp
+    /// (e.g. `2025-01-01T00:00:00Z`).
+    #[arg(long = "created-after", value_name = "RFC3339")]
+    created_after: Option<String>,
+
+    /// Stop after this many matching users.
+    #[arg(long = "limit", value_name = "N")]
+    limit: Option<usize>,
+
+    /// Only include users whose e-mail is in this list.
+    #[arg(long = "include-email", value_name = "EMAIL")]
+    include_email: Vec<String>,
+
+    /// Exclude users whose e-mail is in this list.
+    #[arg(long = "exclude-email", value_name = "EMAIL")]
+    exclude_email: Vec<String>,
+
+    /// Only include users whose username is in this list.
+    #[arg(long = "include-username", value_name = "USERNAME")]
+    include_username: Vec<String>,
+
+    /// Exclude users whose username is in this list.
+    #[arg(long = "exclude-username", value_name = "USERNAME")]
+    exclude_username: Vec<String>,
+
+    /// Treat `emails_file` as the source of truth and converge the pool
+    /// to match it, instead of dumping the pool to CSV.
+    #[arg(long = "reconcile")]
+    reconcile: bool,
+
+    /// When reconciling, also prune pool users absent from `emails_file`.
+    #[arg(long = "prune")]
+    prune: bool,
+
+    /// What to do with a pruned user: disable it (default) or delete it.
+    #[arg(long = "prune-action", value_enum, default_value_t = PruneAction::Disable)]
+    prune_action: PruneAction,
+
+    /// Log the planned reconcile actions without mutating Cognito.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// `MessageAction` used for `AdminCreateUser` calls while reconciling.
+    #[arg(long = "message-action", value_enum, default_value_t = MessageActionArg::Suppress)]
+    message_action: MessageActionArg,
+}
+
+/// What to do with a pool user absent from the reconcile source when
+/// `--prune` is passed.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum PruneAction {
+    /// `AdminDisableUser` the account.
+    Disable,
+    /// `AdminDeleteUser` the account permanently.
+    Delete,
+}
+
+/// CLI-facing mirror of `aws_sdk_cognitoidentityprovider::types::MessageActionType`.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum MessageActionArg {
+    /// Suppress the welcome e-mail/SMS for newly created users.
+    Suppress,
+    /// Resend the welcome e-mail/SMS for newly created users.
+    Resend,
+}
+
+impl From<MessageActionArg> for MessageActionType {
+    fn from(value: MessageActionArg) -> Self {
+        match value {
+            MessageActionArg::Suppress => MessageActionType::Suppress,
+            MessageActionArg::Resend => MessageActionType::Resend,
+        }
+    }
+}
+
+/// Filters applied while exporting users in `sync_users_to_csv`.
+///
+/// These mirror the predicates a typical Cognito reader needs: status,
+/// creation date, explicit allow/deny lists, and a cap on the number of
+/// matching users returned.
+#[derive(Debug, Default, Clone)]
+pub struct SyncFilters {
+    /// Only keep users whose status is `UNCONFIRMED`.
+    pub unconfirmed_only: bool,
+
+    /// Drop users whose status is `UNCONFIRMED`.
+    pub exclude_unconfirmed: bool,
+
+    /// Keep `UNCONFIRMED` users even if `exclude_unconfirmed` is set.
+    pub include_unconfirmed: bool,
+
+    /// Only keep users created at or after this timestamp.
+    pub created_after: Option<AwsDateTime>,
+
+    /// Stop after this many matching users.
+    pub limit: Option<usize>,
+
+    /// Only keep users whose e-mail is in this list.
+    pub include_email: Vec<String>,
+
+    /// Drop users whose e-mail is in this list.
+    pub exclude_email: Vec<String>,
+
+    /// Only keep users whose username is in this list.
+    pub include_username: Vec<String>,
+
+    /// Drop users whose username is in this list.
+    pub exclude_username: Vec<String>,
+}
+
+impl SyncFilters {
+    /// Whether `user` passes every configured predicate.
+    fn matches(&self, username: &str, email: &str, user: &UserType) -> bool {
+        let status = user.user_status();
+
+        if self.unconfirmed_only && status != Some(&UserStatusType::Unconfirmed) {
+            return false;
+        }
+
+        if self.exclude_unconfirmed
+            && !self.include_unconfirmed
+            && !self.unconfirmed_only
+            && status == Some(&UserStatusType::Unconfirmed)
+        {
+            return false;
+        }
+
+        if let Some(cutoff) = &self.created_after {
+            match user.user_create_date() {
+                Some(created) if created >= cutoff => {}
+                _ => return false,
+            }
+        }
+
+        if !self.include_email.is_empty() && !self.include_email.iter().any(|e| e == email) {
+            return false;
+        }
+
+        if self.exclude_email.iter().any(|e| e == email) {
+            return false;
+        }
+
+        if !self.include_username.is_empty()
+            && !self.include_username.iter().any(|u| u == username)
+        {
+            return false;
+        }
+
+        if self.exclude_username.iter().any(|u| u == username) {
+            return false;
+        }
+
+        true
+    }
+
+    /// Like `matches`, but only evaluates the identity predicates
+    /// (`include`/`exclude` email and username lists) and ignores status
+    /// and creation-date. Used by reconcile, where `unconfirmed_only`/
+    /// `exclude_unconfirmed`/`include_unconfirmed`/`created_after` are
+    /// export-time concerns that must not hide a genuinely existing pool
+    /// user from the diff.
+    fn matches_for_reconcile(&self, username: &str, email: &str) -> bool {
+        if !self.include_email.is_empty() && !self.include_email.iter().any(|e| e == email) {
+            return false;
+        }
+
+        if self.exclude_email.iter().any(|e| e == email) {
+            return false;
+        }
+
+        if !self.include_username.is_empty()
+            && !self.include_username.iter().any(|u| u == username)
+        {
+            return false;
+        }
+
+        if self.exclude_username.iter().any(|u| u == username) {
+            return false;
+        }
+
+        true
+    }
+
+    /// A server-side Cognito `ListUsers` filter expression for the
+    /// predicates that Cognito itself can evaluate, or `None` if no
+    /// configured predicate maps cleanly onto a single `filter` clause.
+    fn server_side_filter(&self) -> Option<String> {
+        if self.include_email.len() == 1 && self.exclude_email.is_empty() {
+            return Some(format!("email = \"{}\"", self.include_email[0]));
+        }
+
+        None
+    }
 }
 
 /// Arguments shared by `add` and `del` group operations.
@@ -173,6 +405,44 @@ struct GroupOperationArgs {
     timeout: Option<u64>,
 }
 
+/// Arguments for the `groups` subcommand.
+#[derive(Debug, Parser)]
+struct GroupsArgs {
+    /// Cognito User Pool ID (e.g. us-east-1_XXXXXXXXX).
+    #[arg(long = "pool-id", env = "COGNITO_USER_POOL_ID")]
+    pool_id: String,
+
+    /// Also report the number of members per group.
+    #[arg(long = "with-counts")]
+    with_counts: bool,
+
+    /// Output format.
+    #[arg(long = "format", value_enum, default_value_t = GroupsFormat::Text)]
+    format: GroupsFormat,
+
+    /// Output file; defaults to stdout.
+    #[arg(short = 'f', long = "file", value_name = "PATH")]
+    file: Option<PathBuf>,
+}
+
+/// Output format for the `groups` subcommand.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum GroupsFormat {
+    /// Human-readable lines.
+    Text,
+    /// `group,members` CSV.
+    Csv,
+    /// JSON array of `{"group": ..., "members": ...}`.
+    Json,
+}
+
+/// A Cognito group and, when requested, its member count.
+#[derive(Debug)]
+struct GroupInfo {
+    name: String,
+    members: Option<usize>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -182,38 +452,78 @@ async fn main() -> Result<()> {
 
     match cli.command {
         Commands::Sync(args) => {
+            let created_after = args
+                .created_after
+                .as_deref()
+                .map(|value| AwsDateTime::from_str(value, AwsDateTimeFormat::DateTime))
+                .transpose()
+                .context("failed to parse --created-after as RFC 3339")?;
+
+            let filters = SyncFilters {
+                unconfirmed_only: args.unconfirmed_only,
+                exclude_unconfirmed: args.exclude_unconfirmed,
+                include_unconfirmed: args.include_unconfirmed,
+                created_after,
+                limit: args.limit,
+                include_email: args.include_email,
+                exclude_email: args.exclude_email,
+                include_username: args.include_username,
+                exclude_username: args.exclude_username,
+            };
+
+            let reconcile = args.reconcile;
+            let message_action = args.message_action;
+            let prune = args.prune;
+            let prune_action = args.prune_action;
+            let dry_run = args.dry_run;
+
             let common = CommonOperationArgs {
                 pool_id: args.pool_id,
+                groups: args.groups,
                 emails_file: args.emails_file,
-                concurrency: 1, //args.concurrency,
+                concurrency: args.concurrency.unwrap_or(1),
+                timeout: args.timeout,
+            };
+
+            if reconcile {
+                run_reconcile(
+                    &common,
+                    &filters,
+                    message_action.into(),
+                    prune,
+                    prune_action,
+                    dry_run,
+                )
+                .await?;
+            } else {
+                run_sync(&common, &filters).await?;
+            }
+        }
+        Commands::Add(args) => {
+            let common = CommonOperationArgs {
+                pool_id: args.pool_id,
+                groups: args.groups,
+                emails_file: Some(args.emails_file),
+                concurrency: args.concurrency.unwrap_or(1),
                 timeout: args.timeout,
             };
 
-            run_sync(&common).await?;
-        }
-        _ => unimplemented!(),
-        // Commands::Add(args) => {
-        //     let common = CommonOperationArgs {
-        //         pool_id: args.pool_id,
-        //         groups: args.groups,
-        //         emails_file: Some(args.emails_file),
-        //         concurrency: args.concurrency,
-        //         timeout: args.timeout,
-        //     };
-
-        //     run_add_groups(common).await?;
-        // }
-        // Commands::Del(args) => {
-        //     let common = CommonOperationArgs {
-        //         pool_id: args.pool_id,
-        //         groups: args.groups,
-        //         emails_file: Some(args.emails_file),
-        //         concurrency: args.concurrency,
-        //         timeout: args.timeout,
-        //     };
-
-        //     run_remove_groups(common).await?;
-        // }
+            run_add_groups(&common).await?;
+        }
+        Commands::Del(args) => {
+            let common = CommonOperationArgs {
+                pool_id: args.pool_id,
+                groups: args.groups,
+                emails_file: Some(args.emails_file),
+                concurrency: args.concurrency.unwrap_or(1),
+                timeout: args.timeout,
+            };
+
+            run_remove_groups(&common).await?;
+        }
+        Commands::Groups(args) => {
+            run_groups(args).await?;
+        }
     }
 
     Ok(())
@@ -252,14 +562,18 @@ fn init_tracing(verbose: u8) {
 /// ...
 /// ```
 ///
-/// Source of truth is Cognito: this command dumps all users from the pool.
+/// Source of truth is Cognito: this command dumps users from the pool,
+/// subject to `filters`.
 ///
 /// Behavior:
-/// - Paginates over all Cognito users in the pool.
+/// - Paginates over Cognito users in the pool, applying `filters` to each.
+/// - By default, every user is exported regardless of confirmation status
+///   (matching historical behavior); pass `--exclude-unconfirmed` to drop
+///   `UNCONFIRMED` users, or `--unconfirmed-only` to see only those.
 /// - Extracts the `username` field and the `email` attribute (if present).
 /// - Writes the data as `username,email` to the given output file or stdout.
 /// - Respects the optional `timeout` passed in `CommonOperationArgs`.
-pub async fn run_sync(args: &CommonOperationArgs) -> Result<()> {
+pub async fn run_sync(args: &CommonOperationArgs, filters: &SyncFilters) -> Result<()> {
     info!(
         pool_id = %args.pool_id,
         "Starting users sync from Cognito user pool"
@@ -272,7 +586,7 @@ pub async fn run_sync(args: &CommonOperationArgs) -> Result<()> {
 
     let timeout = args.timeout.map(Duration::from_secs);
 
-    let sync_future = sync_users_to_csv(&client, args);
+    let sync_future = sync_users_to_csv(&client, args, filters);
 
     if let Some(duration) = timeout {
         match tokio::time::timeout(duration, sync_future).await {
@@ -294,55 +608,332 @@ pub async fn run_sync(args: &CommonOperationArgs) -> Result<()> {
     Ok(())
 }
 
-async fn run_add_groups(args: CommonOperationArgs) -> Result<()> {
+/// Live counters for a concurrent batch, shared across worker tasks so
+/// each one can record its own outcome as it finishes.
+#[derive(Debug, Default)]
+struct BatchProgress {
+    total: AtomicUsize,
+    completed: AtomicUsize,
+    failed: AtomicUsize,
+    skipped: AtomicUsize,
+}
+
+impl BatchProgress {
+    fn new(total: usize) -> Arc<Self> {
+        Arc::new(Self {
+            total: AtomicUsize::new(total),
+            ..Self::default()
+        })
+    }
+
+    /// Set the expected total once it becomes known, for batches (like
+    /// reconcile) whose size depends on an async plan computed after the
+    /// tracker is created so a timeout during planning can still log it.
+    fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    fn record_completed(&self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_skipped(&self) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn processed(&self) -> usize {
+        self.completed.load(Ordering::Relaxed)
+            + self.failed.load(Ordering::Relaxed)
+            + self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Emit a throughput line, e.g. "420/1000 processed, 3 failed".
+    fn log_progress(&self) {
+        info!(
+            "{}/{} processed, {} failed",
+            self.processed(),
+            self.total.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed)
+        );
+    }
+
+    fn is_done(&self) -> bool {
+        self.processed() >= self.total.load(Ordering::Relaxed)
+    }
+}
+
+/// Aborts the wrapped task when dropped, so a timed-out or early-returning
+/// batch doesn't leave its progress reporter logging in the background.
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Spawn a background task that periodically logs `progress` throughput
+/// until the batch completes.
+fn spawn_progress_reporter(progress: Arc<BatchProgress>) -> AbortOnDrop {
+    AbortOnDrop(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        interval.tick().await; // first tick fires immediately
+        loop {
+            interval.tick().await;
+            progress.log_progress();
+            if progress.is_done() {
+                break;
+            }
+        }
+    }))
+}
+
+/// Direction of a group-membership batch operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GroupOp {
+    Add,
+    Remove,
+}
+
+/// Outcome of a concurrent `add`/`del` batch: which e-mails were
+/// processed successfully and which failed, paired with the error that
+/// caused the failure so a partial batch still reports what went wrong.
+struct BatchSummary {
+    succeeded: Vec<String>,
+    failed: Vec<(String, anyhow::Error)>,
+}
+
+/// Add every e-mail in `args.emails_file` to every group in `args.groups`.
+pub async fn run_add_groups(args: &CommonOperationArgs) -> Result<()> {
+    run_group_operation(args, GroupOp::Add).await
+}
+
+/// Remove every e-mail in `args.emails_file` from every group in `args.groups`.
+pub async fn run_remove_groups(args: &CommonOperationArgs) -> Result<()> {
+    run_group_operation(args, GroupOp::Remove).await
+}
+
+/// Drive an `add`/`del` group-membership batch over every e-mail in
+/// `args.emails_file`, honoring `args.concurrency` via a bounded
+/// `buffer_unordered` pipeline and `args.timeout` for the whole batch. The
+/// `BatchProgress` tracker is created here, before the timeout wraps the
+/// batch future, so a timed-out batch can still log a final summary.
+async fn run_group_operation(args: &CommonOperationArgs, op: GroupOp) -> Result<()> {
+    let emails_file = args
+        .emails_file
+        .as_ref()
+        .context("emails_file is required for add/del group operations")?;
+
+    if args.groups.is_empty() {
+        return Err(anyhow::anyhow!("at least one --group is required"));
+    }
+
     info!(
         pool_id = %args.pool_id,
-        emails_file = ?args.emails_file,
-        concurrency = ?args.concurrency,
-        timeout = ?args.timeout,
-        "add groups operation requested (not implemented yet)"
+        groups = ?args.groups,
+        concurrency = args.concurrency,
+        ?op,
+        "starting group membership batch"
     );
 
-    if let Some(seconds) = args.timeout {
-        let _timeout = Duration::from_secs(seconds);
-        debug!(?seconds, "add operation timeout configured");
+    let config = aws_config::load_from_env().await;
+    let client = CognitoClient::new(&config);
+
+    let emails = read_emails_file(emails_file).await?;
+    let progress = BatchProgress::new(emails.len());
+    let batch_future = process_group_membership(&client, args, op, emails, Arc::clone(&progress));
+
+    let summary = match args.timeout {
+        Some(seconds) => {
+            let duration = Duration::from_secs(seconds);
+            match tokio::time::timeout(duration, batch_future).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    progress.log_progress();
+                    return Err(anyhow::anyhow!(
+                        "group operation timed out after {duration:?}"
+                    ));
+                }
+            }
+        }
+        None => batch_future.await?,
+    };
+
+    if !summary.failed.is_empty() {
+        for (email, error) in &summary.failed {
+            warn!(%email, %error, "failed to process e-mail");
+        }
     }
 
-    // TODO: implement add-to-groups logic.
+    info!(
+        succeeded = summary.succeeded.len(),
+        failed = summary.failed.len(),
+        "group membership batch completed"
+    );
+
     Ok(())
 }
 
-async fn run_remove_groups(args: CommonOperationArgs) -> Result<()> {
-    info!(
-        pool_id = %args.pool_id,
-        emails_file = ?args.emails_file,
-        concurrency = ?args.concurrency,
-        timeout = ?args.timeout,
-        "remove groups operation requested (not implemented yet)"
-    );
+/// Add/remove each resolved user in `emails` from `args.groups`, running
+/// up to `args.concurrency` e-mails at a time. Failures are collected
+/// rather than aborting the batch so a partial batch still reports which
+/// addresses could not be processed.
+async fn process_group_membership(
+    client: &CognitoClient,
+    args: &CommonOperationArgs,
+    op: GroupOp,
+    emails: Vec<String>,
+    progress: Arc<BatchProgress>,
+) -> Result<BatchSummary> {
+    let concurrency = args.concurrency.max(1);
+    let pool_id = args.pool_id.clone();
+    let groups = args.groups.clone();
+    let _reporter = spawn_progress_reporter(Arc::clone(&progress));
+
+    let results: Vec<(String, Result<()>)> = stream::iter(emails)
+        .map(|email| {
+            group_op_task(
+                client.clone(),
+                pool_id.clone(),
+                groups.clone(),
+                op,
+                Arc::clone(&progress),
+                email,
+            )
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
 
-    if let Some(seconds) = args.timeout {
-        let _timeout = Duration::from_secs(seconds);
-        debug!(?seconds, "remove operation timeout configured");
+    progress.log_progress();
+
+    let mut summary = BatchSummary {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for (email, result) in results {
+        match result {
+            Ok(()) => summary.succeeded.push(email),
+            Err(error) => summary.failed.push((email, error)),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Per-user task for the `add`/`del` pipelines: applies the group
+/// operation to `email` and records the outcome on `progress`. Captures
+/// everything the executor needs so it can just poll this future under
+/// the concurrency limit.
+async fn group_op_task(
+    client: CognitoClient,
+    pool_id: String,
+    groups: Vec<String>,
+    op: GroupOp,
+    progress: Arc<BatchProgress>,
+    email: String,
+) -> (String, Result<()>) {
+    let result = apply_group_op(&client, &pool_id, &email, &groups, op).await;
+
+    match &result {
+        Ok(()) => progress.record_completed(),
+        Err(_) => progress.record_failed(),
+    }
+
+    (email, result)
+}
+
+/// Resolve `email` to a Cognito username and add/remove it from every
+/// group in `groups`.
+async fn apply_group_op(
+    client: &CognitoClient,
+    pool_id: &str,
+    email: &str,
+    groups: &[String],
+    op: GroupOp,
+) -> Result<()> {
+    let username = resolve_username_by_email(client, pool_id, email).await?;
+
+    for group in groups {
+        match op {
+            GroupOp::Add => {
+                client
+                    .admin_add_user_to_group()
+                    .user_pool_id(pool_id)
+                    .username(&username)
+                    .group_name(group)
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to add '{email}' to group '{group}'"))?;
+            }
+            GroupOp::Remove => {
+                client
+                    .admin_remove_user_from_group()
+                    .user_pool_id(pool_id)
+                    .username(&username)
+                    .group_name(group)
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to remove '{email}' from group '{group}'"))?;
+            }
+        }
     }
 
-    // TODO: implement remove-from-groups logic.
     Ok(())
 }
 
-/// Fetch all users from Cognito and write `username,email` to a CSV destination.
+/// Resolve an e-mail address to its Cognito `username` via `ListUsers`
+/// filtered on `email = "..."`.
+async fn resolve_username_by_email(
+    client: &CognitoClient,
+    pool_id: &str,
+    email: &str,
+) -> Result<String> {
+    let response = client
+        .list_users()
+        .user_pool_id(pool_id)
+        .filter(format!("email = \"{email}\""))
+        .limit(1)
+        .send()
+        .await
+        .with_context(|| format!("failed to look up user by email '{email}'"))?;
+
+    let user = response
+        .users()
+        .first()
+        .with_context(|| format!("no Cognito user found for email '{email}'"))?;
+
+    user.username()
+        .map(str::to_owned)
+        .with_context(|| format!("user for email '{email}' has no username"))
+}
+
+/// Fetch users from Cognito and write `username,email` to a CSV destination.
 ///
 /// If `args.emails_file` is set, the CSV is written to that file.
 /// Otherwise, the CSV is written to stdout.
-pub(crate)async fn sync_users_to_csv(client: &CognitoClient, args: &CommonOperationArgs) -> Result<()> {
-    let mut writer: Box<dyn AsyncWrite + Unpin + Send> = if let Some(path) = &args.emails_file {
-        let file = File::create(path)
-            .await
-            .with_context(|| format!("failed to create output file at '{}'", path.display()))?;
-        Box::new(file)
-    } else {
-        Box::new(io::stdout())
-    };
+///
+/// `filters` is applied to every user in the page before it is written:
+/// predicates Cognito can evaluate server-side (currently a single
+/// `--include-email`) are pushed into the `ListUsers` request via
+/// `SyncFilters::server_side_filter`; the rest (status, creation date,
+/// remaining include/exclude lists) are evaluated locally. `filters.limit`
+/// stops the export early by breaking out mid-page as soon as the limit is
+/// reached; page size always stays at the 60-user Cognito max, since the
+/// number of users *matching* `filters` on a given page can be far smaller
+/// than the number scanned, and shrinking requested pages accordingly would
+/// multiply `ListUsers` calls instead of cutting them.
+pub(crate)async fn sync_users_to_csv(
+    client: &CognitoClient,
+    args: &CommonOperationArgs,
+    filters: &SyncFilters,
+) -> Result<()> {
+    let mut writer = open_output(&args.emails_file).await?;
 
     // CSV header
     writer
@@ -350,15 +941,26 @@ pub(crate)async fn sync_users_to_csv(client: &CognitoClient, args: &CommonOperat
         .await
         .context("failed to write CSV header")?;
 
+    let server_side_filter = filters.server_side_filter();
+
     let mut total_users = 0usize;
     let mut pagination_token: Option<String> = None;
 
-    loop {
+    'pages: loop {
+        // 60 is the documented default max page size for Cognito ListUsers.
+        // Always request a full page: `filters.limit` is enforced per-row
+        // below, not by shrinking the request, since filtered matches on a
+        // page can be far sparser than the raw users Cognito returns.
+        let page_size = 60;
+
         let mut request = client
             .list_users()
             .user_pool_id(&args.pool_id)
-            // 60 is the documented default max page size for Cognito ListUsers.
-            .limit(60);
+            .limit(page_size);
+
+        if let Some(ref filter) = server_side_filter {
+            request = request.filter(filter);
+        }
 
         if let Some(ref token) = pagination_token {
             request = request.pagination_token(token);
@@ -372,6 +974,10 @@ pub(crate)async fn sync_users_to_csv(client: &CognitoClient, args: &CommonOperat
         for user in response.users() {
             let (username, email) = extract_username_and_email(user);
 
+            if !filters.matches(&username, &email, user) {
+                continue;
+            }
+
             // If you prefer to skip users without email, you can check `email.is_empty()`.
             let line = format!("{username},{email}\n");
             writer
@@ -380,6 +986,10 @@ pub(crate)async fn sync_users_to_csv(client: &CognitoClient, args: &CommonOperat
                 .context("failed to write CSV row")?;
 
             total_users += 1;
+
+            if filters.limit.is_some_and(|limit| total_users >= limit) {
+                break 'pages;
+            }
         }
 
         pagination_token = response
@@ -397,6 +1007,19 @@ pub(crate)async fn sync_users_to_csv(client: &CognitoClient, args: &CommonOperat
     Ok(())
 }
 
+/// Open the `AsyncWrite` destination for an operation: `path` if given,
+/// otherwise stdout.
+async fn open_output(path: &Option<PathBuf>) -> Result<Box<dyn AsyncWrite + Unpin + Send>> {
+    if let Some(path) = path {
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("failed to create output file at '{}'", path.display()))?;
+        Ok(Box::new(file))
+    } else {
+        Ok(Box::new(io::stdout()))
+    }
+}
+
 /// Extract the `username` and `email` attribute from a Cognito `UserType`.
 fn extract_username_and_email(user: &UserType) -> (String, String) {
     let username = user.username().unwrap_or_default().to_string();
@@ -412,3 +1035,621 @@ fn extract_username_and_email(user: &UserType) -> (String, String) {
     (username, email)
 }
 
+/// Read one e-mail per line from `path`, trimming whitespace and skipping
+/// blank lines.
+async fn read_emails_file(path: &PathBuf) -> Result<Vec<String>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read emails file at '{}'", path.display()))?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// List every user in the pool as `(username, email)` pairs, for use as
+/// the reconcile diff's view of "what currently exists".
+///
+/// Only the identity predicates in `filters` apply here — `include`/
+/// `exclude` email and username lists, via `SyncFilters::matches_for_reconcile`
+/// — since those describe accounts to leave out of reconciliation
+/// entirely. `unconfirmed_only`/`exclude_unconfirmed`/`include_unconfirmed`/
+/// `created_after` are export-time concerns for `sync_users_to_csv` and are
+/// deliberately NOT applied here: an `UNCONFIRMED` pool user is still a
+/// real, existing account and must not be treated as missing just because
+/// `sync --exclude-unconfirmed` would have hidden it from the CSV export.
+/// `filters.limit` is also ignored — capping this listing would make the
+/// reconcile diff see a partial pool; callers must reject `--limit`
+/// combined with `--reconcile` instead of passing it through.
+async fn list_all_pool_users(
+    client: &CognitoClient,
+    pool_id: &str,
+    filters: &SyncFilters,
+) -> Result<Vec<(String, String)>> {
+    let mut users = Vec::new();
+    let mut pagination_token: Option<String> = None;
+    let server_side_filter = filters.server_side_filter();
+
+    loop {
+        let mut request = client
+            .list_users()
+            .user_pool_id(pool_id)
+            // 60 is the documented default max page size for Cognito ListUsers.
+            .limit(60);
+
+        if let Some(ref filter) = server_side_filter {
+            request = request.filter(filter);
+        }
+
+        if let Some(ref token) = pagination_token {
+            request = request.pagination_token(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("failed to call Cognito ListUsers")?;
+
+        for user in response.users() {
+            let (username, email) = extract_username_and_email(user);
+
+            if filters.matches_for_reconcile(&username, &email) {
+                users.push((username, email));
+            }
+        }
+
+        pagination_token = response
+            .pagination_token()
+            .map(|token| token.to_owned());
+
+        if pagination_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(users)
+}
+
+/// Reconcile a Cognito user pool so it matches the e-mails listed in
+/// `args.emails_file`: missing accounts are created (optionally placed
+/// into `args.groups`) and, when `prune` is set, accounts present in the
+/// pool but absent from the file are disabled or deleted. `dry_run` logs
+/// the planned actions without mutating Cognito. `filters` is applied to
+/// the pool listing used to compute the diff; `filters.limit` is rejected
+/// since capping the pool scan would corrupt the reconcile diff. The
+/// `BatchProgress` tracker is created here, before the timeout wraps the
+/// reconcile future, so a timed-out reconcile can still log a final
+/// summary (its total is filled in once the diff is computed).
+pub async fn run_reconcile(
+    args: &CommonOperationArgs,
+    filters: &SyncFilters,
+    message_action: MessageActionType,
+    prune: bool,
+    prune_action: PruneAction,
+    dry_run: bool,
+) -> Result<()> {
+    let source_file = args
+        .emails_file
+        .as_ref()
+        .context("emails_file is required to reconcile a pool")?;
+
+    if filters.limit.is_some() {
+        return Err(anyhow::anyhow!(
+            "--limit cannot be combined with --reconcile: it would make the reconcile diff see a partial pool"
+        ));
+    }
+
+    info!(
+        pool_id = %args.pool_id,
+        groups = ?args.groups,
+        prune,
+        dry_run,
+        "starting pool reconciliation"
+    );
+
+    let config = aws_config::load_from_env().await;
+    let client = CognitoClient::new(&config);
+
+    let progress = BatchProgress::new(0);
+    let reconcile_future = reconcile_pool(
+        &client,
+        args,
+        filters,
+        source_file,
+        message_action,
+        prune,
+        prune_action,
+        dry_run,
+        Arc::clone(&progress),
+    );
+
+    match args.timeout {
+        Some(seconds) => {
+            let duration = Duration::from_secs(seconds);
+            match tokio::time::timeout(duration, reconcile_future).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    progress.log_progress();
+                    return Err(anyhow::anyhow!(
+                        "reconcile operation timed out after {duration:?}"
+                    ));
+                }
+            }
+        }
+        None => reconcile_future.await?,
+    }
+
+    info!("pool reconciliation completed");
+    Ok(())
+}
+
+/// Compute and apply the set difference between `source_file` and the
+/// pool's current users. The identity predicates of `filters` (include/
+/// exclude e-mail and username lists) are applied to both the pool listing
+/// and the source e-mails (each source e-mail standing in as the username
+/// it would be created with) before the diff is computed, so e.g.
+/// `--exclude-email` keeps those accounts out of the reconcile entirely.
+/// Status and creation-date filters are not applied.
+/// `filters.limit` is rejected by the caller rather than honored here.
+/// `progress` is created by the caller (so a timeout can still log it) and
+/// has its total filled in here once the diff is computed.
+///
+/// Both sides of the diff are lowercased before comparison, since an
+/// operator's source file and what a user typed at signup routinely differ
+/// only in case, and comparing raw strings would otherwise re-"discover"
+/// the same account as missing on every run and create a duplicate.
+/// `to_create` is derived from a `HashSet` difference so a duplicate line
+/// in `source_file` also only produces one `AdminCreateUser` call.
+async fn reconcile_pool(
+    client: &CognitoClient,
+    args: &CommonOperationArgs,
+    filters: &SyncFilters,
+    source_file: &PathBuf,
+    message_action: MessageActionType,
+    prune: bool,
+    prune_action: PruneAction,
+    dry_run: bool,
+    progress: Arc<BatchProgress>,
+) -> Result<()> {
+    let source_emails = read_emails_file(source_file).await?;
+    let source_set: HashSet<String> = source_emails
+        .iter()
+        .filter(|email| filters.matches_for_reconcile(email, email))
+        .map(|email| email.to_lowercase())
+        .collect();
+
+    let pool_users = list_all_pool_users(client, &args.pool_id, filters).await?;
+    let pool_emails: HashSet<String> = pool_users
+        .iter()
+        .map(|(_, email)| email.to_lowercase())
+        .collect();
+
+    let to_create: Vec<String> = source_set.difference(&pool_emails).cloned().collect();
+
+    let to_prune: Vec<(String, String)> = if prune {
+        pool_users
+            .into_iter()
+            .filter(|(_, email)| !email.is_empty() && !source_set.contains(&email.to_lowercase()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    info!(
+        to_create = to_create.len(),
+        to_prune = to_prune.len(),
+        "reconcile plan computed"
+    );
+
+    let concurrency = args.concurrency.max(1);
+    let pool_id = args.pool_id.clone();
+    let groups = args.groups.clone();
+    progress.set_total(to_create.len() + to_prune.len());
+    let _reporter = spawn_progress_reporter(Arc::clone(&progress));
+
+    let create_results: Vec<(String, Result<()>)> = stream::iter(to_create)
+        .map(|email| {
+            create_user_task(
+                client.clone(),
+                pool_id.clone(),
+                groups.clone(),
+                message_action.clone(),
+                dry_run,
+                Arc::clone(&progress),
+                email,
+            )
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let prune_results: Vec<((String, String), Result<()>)> = stream::iter(to_prune)
+        .map(|user| {
+            prune_user_task(
+                client.clone(),
+                pool_id.clone(),
+                prune_action,
+                dry_run,
+                Arc::clone(&progress),
+                user,
+            )
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    progress.log_progress();
+
+    for (email, result) in &create_results {
+        if let Err(error) = result {
+            warn!(%email, %error, "failed to create user during reconcile");
+        }
+    }
+
+    for ((username, email), result) in &prune_results {
+        if let Err(error) = result {
+            warn!(%username, %email, %error, "failed to prune user during reconcile");
+        }
+    }
+
+    Ok(())
+}
+
+/// Per-user task for the reconcile create step: create the Cognito user
+/// (or, in dry-run mode, just log the plan), add it to `groups`, and
+/// record the outcome on `progress`.
+async fn create_user_task(
+    client: CognitoClient,
+    pool_id: String,
+    groups: Vec<String>,
+    message_action: MessageActionType,
+    dry_run: bool,
+    progress: Arc<BatchProgress>,
+    email: String,
+) -> (String, Result<()>) {
+    let result = create_user_with_groups(&client, &pool_id, &email, &groups, message_action, dry_run).await;
+
+    if dry_run {
+        progress.record_skipped();
+    } else {
+        match &result {
+            Ok(()) => progress.record_completed(),
+            Err(_) => progress.record_failed(),
+        }
+    }
+
+    (email, result)
+}
+
+/// `AdminCreateUser` for `email`, then add it to every group in `groups`.
+async fn create_user_with_groups(
+    client: &CognitoClient,
+    pool_id: &str,
+    email: &str,
+    groups: &[String],
+    message_action: MessageActionType,
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        info!(%email, "dry-run: would create user and add to groups");
+        return Ok(());
+    }
+
+    let attribute = AttributeType::builder()
+        .name("email")
+        .value(email)
+        .build()
+        .context("failed to build email attribute")?;
+
+    client
+        .admin_create_user()
+        .user_pool_id(pool_id)
+        .username(email)
+        .user_attributes(attribute)
+        .message_action(message_action)
+        .send()
+        .await
+        .with_context(|| format!("failed to create user '{email}'"))?;
+
+    for group in groups {
+        client
+            .admin_add_user_to_group()
+            .user_pool_id(pool_id)
+            .username(email)
+            .group_name(group)
+            .send()
+            .await
+            .with_context(|| format!("failed to add new user '{email}' to group '{group}'"))?;
+    }
+
+    Ok(())
+}
+
+/// Per-user task for the reconcile prune step: disable/delete the
+/// Cognito user (or, in dry-run mode, just log the plan) and record the
+/// outcome on `progress`.
+async fn prune_user_task(
+    client: CognitoClient,
+    pool_id: String,
+    prune_action: PruneAction,
+    dry_run: bool,
+    progress: Arc<BatchProgress>,
+    user: (String, String),
+) -> ((String, String), Result<()>) {
+    let (username, email) = &user;
+    let result = prune_user(client, pool_id, username, email, prune_action, dry_run).await;
+
+    if dry_run {
+        progress.record_skipped();
+    } else {
+        match &result {
+            Ok(()) => progress.record_completed(),
+            Err(_) => progress.record_failed(),
+        }
+    }
+
+    (user, result)
+}
+
+/// `AdminDisableUser` or `AdminDeleteUser`, depending on `prune_action`.
+async fn prune_user(
+    client: CognitoClient,
+    pool_id: String,
+    username: &str,
+    email: &str,
+    prune_action: PruneAction,
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        info!(%username, %email, ?prune_action, "dry-run: would prune user");
+        return Ok(());
+    }
+
+    match prune_action {
+        PruneAction::Disable => {
+            client
+                .admin_disable_user()
+                .user_pool_id(pool_id)
+                .username(username)
+                .send()
+                .await
+                .with_context(|| format!("failed to disable user '{username}'"))?;
+        }
+        PruneAction::Delete => {
+            client
+                .admin_delete_user()
+                .user_pool_id(pool_id)
+                .username(username)
+                .send()
+                .await
+                .with_context(|| format!("failed to delete user '{username}'"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List the groups in `args.pool_id`, optionally with their member counts,
+/// and write them to `args.file` (or stdout) in `args.format`. With
+/// `--with-counts`, a group whose count fails to fetch is logged as a
+/// warning and still listed (with no count) rather than aborting the
+/// whole listing.
+pub async fn run_groups(args: GroupsArgs) -> Result<()> {
+    info!(
+        pool_id = %args.pool_id,
+        with_counts = args.with_counts,
+        "listing pool groups"
+    );
+
+    let config = aws_config::load_from_env().await;
+    let client = CognitoClient::new(&config);
+
+    let groups = list_all_pool_groups(&client, &args.pool_id).await?;
+
+    let infos = if args.with_counts {
+        let results: Vec<(String, Result<usize>)> = stream::iter(groups)
+            .map(|name| count_group_members(&client, &args.pool_id, name))
+            .buffered(4)
+            .collect()
+            .await;
+
+        results
+            .into_iter()
+            .map(|(name, result)| match result {
+                Ok(members) => GroupInfo {
+                    name,
+                    members: Some(members),
+                },
+                Err(error) => {
+                    warn!(group = %name, %error, "failed to count group members");
+                    GroupInfo {
+                        name,
+                        members: None,
+                    }
+                }
+            })
+            .collect()
+    } else {
+        groups
+            .into_iter()
+            .map(|name| GroupInfo { name, members: None })
+            .collect()
+    };
+
+    let mut writer = open_output(&args.file).await?;
+    write_groups(&mut writer, &infos, args.format).await?;
+
+    info!(groups = infos.len(), "group listing completed");
+    Ok(())
+}
+
+/// Page through Cognito `ListGroups` and return every group name in the pool.
+async fn list_all_pool_groups(client: &CognitoClient, pool_id: &str) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut next_token: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .list_groups()
+            .user_pool_id(pool_id)
+            // 60 is the documented default max page size for Cognito ListGroups.
+            .limit(60);
+
+        if let Some(ref token) = next_token {
+            request = request.next_token(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("failed to call Cognito ListGroups")?;
+
+        names.extend(
+            response
+                .groups()
+                .iter()
+                .filter_map(GroupType::group_name)
+                .map(str::to_owned),
+        );
+
+        next_token = response.next_token().map(str::to_owned);
+
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(names)
+}
+
+/// Count the members of `group` via `ListUsersInGroup`, paginating until
+/// exhausted. Returns the group name alongside the result so a failure on
+/// one group (e.g. a transient throttle) doesn't lose the groups that
+/// succeeded around it.
+async fn count_group_members(
+    client: &CognitoClient,
+    pool_id: &str,
+    group: String,
+) -> (String, Result<usize>) {
+    let mut members = 0usize;
+    let mut next_token: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .list_users_in_group()
+            .user_pool_id(pool_id)
+            .group_name(&group)
+            .limit(60);
+
+        if let Some(ref token) = next_token {
+            request = request.next_token(token);
+        }
+
+        let response = match request
+            .send()
+            .await
+            .with_context(|| format!("failed to call Cognito ListUsersInGroup for '{group}'"))
+        {
+            Ok(response) => response,
+            Err(error) => return (group, Err(error)),
+        };
+
+        members += response.users().len();
+        next_token = response.next_token().map(str::to_owned);
+
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    (group, Ok(members))
+}
+
+/// Write `infos` to `writer` in the requested `format`.
+async fn write_groups(
+    writer: &mut (dyn AsyncWrite + Unpin + Send),
+    infos: &[GroupInfo],
+    format: GroupsFormat,
+) -> Result<()> {
+    match format {
+        GroupsFormat::Text => {
+            for info in infos {
+                let line = match info.members {
+                    Some(members) => format!("{} ({members} members)\n", info.name),
+                    None => format!("{}\n", info.name),
+                };
+                writer
+                    .write_all(line.as_bytes())
+                    .await
+                    .context("failed to write group listing")?;
+            }
+        }
+        GroupsFormat::Csv => {
+            writer
+                .write_all(b"group,members\n")
+                .await
+                .context("failed to write CSV header")?;
+            for info in infos {
+                let members = info
+                    .members
+                    .map(|count| count.to_string())
+                    .unwrap_or_default();
+                writer
+                    .write_all(format!("{},{}\n", csv_field(&info.name), members).as_bytes())
+                    .await
+                    .context("failed to write CSV row")?;
+            }
+        }
+        GroupsFormat::Json => {
+            let entries: Vec<String> = infos
+                .iter()
+                .map(|info| match info.members {
+                    Some(members) => {
+                        format!("{{\"group\":{},\"members\":{members}}}", json_string(&info.name))
+                    }
+                    None => format!("{{\"group\":{},\"members\":null}}", json_string(&info.name)),
+                })
+                .collect();
+            let json = format!("[{}]\n", entries.join(","));
+            writer
+                .write_all(json.as_bytes())
+                .await
+                .context("failed to write JSON output")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render `value` as a CSV field, quoting and escaping it if it contains a
+/// comma, quote, or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Render `value` as a quoted JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+